@@ -0,0 +1,168 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::anyhow::Result;
+
+/// An in-memory sound asset that can be decoded and played any number
+/// of times.
+#[derive(Clone)]
+pub struct Sound {
+    bytes: Arc<[u8]>,
+}
+
+impl Sound {
+    /// Loads a sound from raw encoded bytes (wav, ogg, mp3, flac; see
+    /// rodio's `Decoder` for the exact set of supported formats).
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self { bytes: bytes.into() }
+    }
+
+    /// Loads a sound from a file on disk.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let bytes = fs::read(path)?;
+        Ok(Self::from_bytes(bytes))
+    }
+
+    fn decoder(&self) -> Result<rodio::Decoder<Cursor<Arc<[u8]>>>> {
+        Ok(rodio::Decoder::new(Cursor::new(self.bytes.clone()))?)
+    }
+}
+
+/// How a `Sound` should be played; passed to `AudioContext::play_with`.
+#[derive(Debug, Clone, Copy)]
+pub struct PlayOptions {
+    pub looping: bool,
+    pub volume: f32,
+    pub pitch: f32,
+}
+
+impl Default for PlayOptions {
+    fn default() -> Self {
+        Self {
+            looping: false,
+            volume: 1.0,
+            pitch: 1.0,
+        }
+    }
+}
+
+/// A handle to a sound that is (or was) playing.
+///
+/// For a fire-and-forget `play`, dropping the handle does not stop
+/// playback; for a persistent source you intend to control over time,
+/// hold onto it for as long as you need `pause`/`resume`/`stop`.
+pub struct Source {
+    sink: Arc<rodio::Sink>,
+}
+
+impl Source {
+    pub fn pause(&self) {
+        self.sink.pause();
+    }
+
+    pub fn resume(&self) {
+        self.sink.play();
+    }
+
+    pub fn stop(&self) {
+        self.sink.stop();
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.sink.set_volume(volume);
+    }
+
+    pub fn set_pitch(&self, pitch: f32) {
+        self.sink.set_speed(pitch);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+}
+
+// Kept alive only so the output stream isn't torn down; rodio requires
+// this to outlive every sink built from its handle.
+struct OutputStream {
+    _stream: rodio::OutputStream,
+    stream_handle: rodio::OutputStreamHandle,
+}
+
+/// Audio playback, backed by a `rodio` output stream that lives for the
+/// program's lifetime.
+///
+/// Opening the output stream can fail (e.g. headless CI, containers
+/// with no sound device); when it does, `AudioContext` is still usable,
+/// it just reports that failure from every method that would otherwise
+/// produce sound, rather than taking the whole engine down at startup.
+pub struct AudioContext {
+    stream: std::result::Result<OutputStream, String>,
+    master_volume: f32,
+    // Fire-and-forget sinks are kept here purely so they aren't dropped
+    // (and thus silenced) before they finish playing.
+    live_sinks: Vec<Arc<rodio::Sink>>,
+}
+
+impl AudioContext {
+    pub(crate) fn new() -> Self {
+        let stream = rodio::OutputStream::try_default()
+            .map(|(stream, stream_handle)| OutputStream {
+                _stream: stream,
+                stream_handle,
+            })
+            .map_err(|err| err.to_string());
+        Self {
+            stream,
+            master_volume: 1.0,
+            live_sinks: Vec::new(),
+        }
+    }
+
+    /// Whether an audio output device was successfully opened. If this
+    /// is `false`, `play`/`play_with` will always return `Err`.
+    pub fn is_available(&self) -> bool {
+        self.stream.is_ok()
+    }
+
+    /// The volume applied on top of each source's own volume when it
+    /// starts playing. Does not retroactively affect already-playing
+    /// sources.
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume;
+    }
+
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
+    }
+
+    /// Plays `sound` once, fire-and-forget, at default volume/pitch.
+    pub fn play(&mut self, sound: &Sound) -> Result<Source> {
+        self.play_with(sound, PlayOptions::default())
+    }
+
+    /// Plays `sound` with explicit volume/pitch/looping, returning a
+    /// handle that can pause, resume, stop, or retune it while it
+    /// plays.
+    ///
+    /// Fails if no audio output device is available; see `is_available`.
+    pub fn play_with(&mut self, sound: &Sound, options: PlayOptions) -> Result<Source> {
+        let stream = self
+            .stream
+            .as_ref()
+            .map_err(|err| crate::anyhow::anyhow!("no audio output device is available: {}", err))?;
+        let sink = rodio::Sink::try_new(&stream.stream_handle)?;
+        sink.set_volume(options.volume * self.master_volume);
+        sink.set_speed(options.pitch);
+        if options.looping {
+            sink.append(sound.decoder()?.repeat_infinite());
+        } else {
+            sink.append(sound.decoder()?);
+        }
+        let sink = Arc::new(sink);
+        self.live_sinks.retain(|s| !s.empty());
+        self.live_sinks.push(sink.clone());
+        Ok(Source { sink })
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::anyhow::Result;
+use crate::audio::AudioContext;
+use crate::fnv::FnvHashMap;
+use crate::fnv::FnvHashSet;
+use crate::input::Action;
+use crate::input::ActionMap;
+use crate::input::ActionMapHandle;
+use crate::time::TimeContext;
+use crate::window::GamepadCommand;
+use crate::Axis;
+use crate::DeviceId;
+use crate::GamepadButton;
+use crate::Key;
+use crate::MouseButton;
+
+/// Shared state handed to most `Game` callbacks.
+///
+/// Holds everything about the running application that isn't specific
+/// to a single frame's rendering: window control, polling-based input
+/// state mirrored from the `key_*`/`mouse_*`/`gamepad_*` hooks, the
+/// installed action map, gamepad rumble, audio, and frame timing.
+pub struct AppContext {
+    pub(crate) should_exit: bool,
+    pub(crate) keys_down: FnvHashSet<Key>,
+    pub(crate) keys_down_prev: FnvHashSet<Key>,
+    pub(crate) mouse_buttons_down: FnvHashSet<MouseButton>,
+    pub(crate) mouse_buttons_down_prev: FnvHashSet<MouseButton>,
+    pub(crate) mouse_position: [f32; 2],
+    pub(crate) gamepad_buttons_down: FnvHashSet<(DeviceId, GamepadButton)>,
+    // Indexed by `GamepadButton` alone (device-agnostic) for
+    // `ActionMap`'s `Single`/`Chord` bindings, which don't care which
+    // pad it came from.
+    pub(crate) gamepad_buttons_down_any_device: FnvHashSet<GamepadButton>,
+    pub(crate) gamepad_axes: FnvHashMap<(DeviceId, Axis), f32>,
+    // Indexed by `Axis` alone (device-agnostic) for `ActionMap`'s
+    // `GamepadAxis` binding, which doesn't care which pad it came from.
+    pub(crate) gamepad_axes_any_device: HashMap<Axis, f32>,
+    pub(crate) gamepad_rumble_supported: FnvHashSet<DeviceId>,
+    pub(crate) gamepad_commands: Option<mpsc::Sender<GamepadCommand>>,
+    pub(crate) action_handler: Option<Box<dyn ActionMapHandle>>,
+    pub(crate) audio: AudioContext,
+    pub(crate) time: TimeContext,
+}
+
+impl AppContext {
+    pub(crate) fn new() -> Result<Self> {
+        Ok(Self {
+            should_exit: false,
+            keys_down: FnvHashSet::default(),
+            keys_down_prev: FnvHashSet::default(),
+            mouse_buttons_down: FnvHashSet::default(),
+            mouse_buttons_down_prev: FnvHashSet::default(),
+            mouse_position: [0.0, 0.0],
+            gamepad_buttons_down: FnvHashSet::default(),
+            gamepad_buttons_down_any_device: FnvHashSet::default(),
+            gamepad_axes: FnvHashMap::default(),
+            gamepad_axes_any_device: HashMap::new(),
+            gamepad_rumble_supported: FnvHashSet::default(),
+            gamepad_commands: None,
+            action_handler: None,
+            audio: AudioContext::new(),
+            time: TimeContext::new(),
+        })
+    }
+
+    /// Requests that the application exit at the next opportunity.
+    pub fn exit(&mut self) {
+        self.should_exit = true;
+    }
+
+    /// Audio playback for the lifetime of the program.
+    pub fn audio(&mut self) -> &mut AudioContext {
+        &mut self.audio
+    }
+
+    /// Frame timing: delta time, fps, tick count, and total elapsed
+    /// time.
+    pub fn time(&self) -> &TimeContext {
+        &self.time
+    }
+
+    pub(crate) fn tick_time(&mut self) {
+        self.time.tick();
+    }
+
+    /// Whether `key` is currently held down.
+    pub fn is_key_pressed(&self, key: Key) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    /// All keys currently held down.
+    pub fn pressed_keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.keys_down.iter().copied()
+    }
+
+    /// Whether `key` transitioned from released to pressed since the
+    /// previous `update` call.
+    pub fn key_just_pressed(&self, key: Key) -> bool {
+        self.keys_down.contains(&key) && !self.keys_down_prev.contains(&key)
+    }
+
+    /// Whether `key` transitioned from pressed to released since the
+    /// previous `update` call.
+    pub fn key_just_released(&self, key: Key) -> bool {
+        !self.keys_down.contains(&key) && self.keys_down_prev.contains(&key)
+    }
+
+    /// Whether `button` is currently held down.
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_down.contains(&button)
+    }
+
+    /// Whether `button` transitioned from released to pressed since the
+    /// previous `update` call.
+    pub fn mouse_button_just_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_down.contains(&button) && !self.mouse_buttons_down_prev.contains(&button)
+    }
+
+    /// Whether `button` transitioned from pressed to released since the
+    /// previous `update` call.
+    pub fn mouse_button_just_released(&self, button: MouseButton) -> bool {
+        !self.mouse_buttons_down.contains(&button) && self.mouse_buttons_down_prev.contains(&button)
+    }
+
+    /// The last-reported cursor position.
+    pub fn mouse_position(&self) -> [f32; 2] {
+        self.mouse_position
+    }
+
+    /// Whether `button` on gamepad `dev` is currently held down.
+    pub fn is_gamepad_button_pressed(&self, dev: DeviceId, button: GamepadButton) -> bool {
+        self.gamepad_buttons_down.contains(&(dev, button))
+    }
+
+    /// The last-reported value of `axis` on gamepad `dev`, or `0.0` if
+    /// no event for it has been received yet.
+    pub fn gamepad_axis(&self, dev: DeviceId, axis: Axis) -> f32 {
+        self.gamepad_axes.get(&(dev, axis)).copied().unwrap_or(0.0)
+    }
+
+    /// Whether gamepad `dev` reports force-feedback support.
+    pub fn gamepad_supports_rumble(&self, dev: DeviceId) -> bool {
+        self.gamepad_rumble_supported.contains(&dev)
+    }
+
+    /// Plays a dual-motor rumble effect on gamepad `dev` for `duration`,
+    /// replacing any rumble already playing on it. `strong` and `weak`
+    /// are motor magnitudes in `[0.0, 1.0]`.
+    pub fn set_gamepad_rumble(
+        &mut self,
+        dev: DeviceId,
+        strong: f32,
+        weak: f32,
+        duration: Duration,
+    ) -> Result<()> {
+        self.send_gamepad_command(GamepadCommand::SetRumble {
+            dev,
+            strong,
+            weak,
+            duration,
+        })
+    }
+
+    /// Stops any rumble effect currently playing on gamepad `dev`.
+    pub fn stop_gamepad_rumble(&mut self, dev: DeviceId) -> Result<()> {
+        self.send_gamepad_command(GamepadCommand::StopRumble { dev })
+    }
+
+    fn send_gamepad_command(&mut self, command: GamepadCommand) -> Result<()> {
+        let tx = self
+            .gamepad_commands
+            .as_ref()
+            .ok_or_else(|| crate::anyhow::anyhow!("gamepad support is not enabled"))?;
+        tx.send(command)
+            .map_err(|_| crate::anyhow::anyhow!("gamepad thread is no longer running"))
+    }
+
+    /// Rotates the previous-frame input snapshot. Called once per
+    /// `update` tick by the window event loop, after `Game::update`
+    /// returns, so that `update` itself can still compare against the
+    /// snapshot taken at the end of the prior frame.
+    pub(crate) fn rotate_input_snapshot(&mut self) {
+        self.keys_down_prev = self.keys_down.clone();
+        self.mouse_buttons_down_prev = self.mouse_buttons_down.clone();
+    }
+
+    /// Installs an `ActionMap<A>`, replacing any previously installed
+    /// action map (even one for a different `A`).
+    pub fn set_action_map<A: Action>(&mut self, map: ActionMap<A>) {
+        self.action_handler = Some(Box::new(map));
+    }
+
+    /// The installed `ActionMap<A>`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no action map has been installed, or if it was
+    /// installed with a different action type.
+    pub fn actions<A: Action>(&self) -> &ActionMap<A> {
+        self.action_handler
+            .as_ref()
+            .expect("no ActionMap installed; call AppContext::set_action_map first")
+            .as_any()
+            .downcast_ref()
+            .expect("ActionMap installed for a different action type")
+    }
+
+    /// Mutable access to the installed `ActionMap<A>`.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `actions`.
+    pub fn actions_mut<A: Action>(&mut self) -> &mut ActionMap<A> {
+        self.action_handler
+            .as_mut()
+            .expect("no ActionMap installed; call AppContext::set_action_map first")
+            .as_any_mut()
+            .downcast_mut()
+            .expect("ActionMap installed for a different action type")
+    }
+
+    pub(crate) fn tick_action_map(&mut self) {
+        if let Some(handler) = &mut self.action_handler {
+            handler.tick(
+                &self.keys_down,
+                &self.mouse_buttons_down,
+                &self.gamepad_buttons_down_any_device,
+                &self.gamepad_axes_any_device,
+            );
+        }
+    }
+}
+
+/// Context passed to `Game::render` for the duration of a single frame.
+pub struct RenderContext<'a> {
+    actx: &'a mut AppContext,
+}
+
+impl<'a> RenderContext<'a> {
+    pub(crate) fn new(actx: &'a mut AppContext) -> Self {
+        Self { actx }
+    }
+
+    /// Access the `AppContext` for the duration of this render.
+    pub fn actx(&mut self) -> &mut AppContext {
+        self.actx
+    }
+}
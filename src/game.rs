@@ -4,6 +4,7 @@ use crate::Axis;
 use crate::DeviceId;
 use crate::GamepadButton;
 use crate::Key;
+use crate::KeyMods;
 use crate::MouseButton;
 use crate::RenderContext;
 
@@ -27,6 +28,14 @@ where
     /// Called to check if the game should be updated
     fn update(&mut self, actx: &mut AppContext) -> Result<()>;
 
+    /// Called at a fixed rate when `Options::fixed_update_hz` is set,
+    /// zero or more times per frame, independently of the variable-rate
+    /// `update`/`render`. Use this for physics or other simulation code
+    /// that needs a deterministic step size.
+    fn fixed_update(&mut self, actx: &mut AppContext) -> Result<()> {
+        Ok(())
+    }
+
     /// Called when drawing on the screen is requested
     ///
     /// The RenderContext can retrieve the AppContext if needed with
@@ -52,12 +61,9 @@ where
     /// The default behavior of this method is to exit when Escape is pressed
     ///
     /// NOTE, not all keys may be recognized. If it isn't, this method
-    /// will not get called for those keys.
-    ///
-    /// In the future, there should be a separate 'key_scancode_*' method
-    /// so that even if the key is not recognized, the raw scancode can be
-    /// passed to the client to process.
-    fn key_pressed(&mut self, actx: &mut AppContext, key: Key) -> Result<()> {
+    /// will not get called for those keys; use `key_scancode_pressed`
+    /// to see every physical key regardless of recognition.
+    fn key_pressed(&mut self, actx: &mut AppContext, key: Key, mods: KeyMods) -> Result<()> {
         if let Key::Escape = key {
             actx.exit();
         }
@@ -67,12 +73,22 @@ where
     /// Called to notify the game that a key was released.
     ///
     /// NOTE, not all keys may be recognized. If it isn't, this method
-    /// will not get called for those keys.
-    ///
-    /// In the future, there should be a separate 'key_scancode_*' method
-    /// so that even if the key is not recognized, the raw scancode can be
-    /// passed to the client to process.
-    fn key_released(&mut self, actx: &mut AppContext, key: Key) -> Result<()> {
+    /// will not get called for those keys; use `key_scancode_released`
+    /// to see every physical key regardless of recognition.
+    fn key_released(&mut self, actx: &mut AppContext, key: Key, mods: KeyMods) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called on every physical key press, keyed by the OS/hardware
+    /// scancode rather than the symbolic `Key`. Fires in addition to
+    /// `key_pressed` when the key maps to a known `Key`, and alone when
+    /// it doesn't.
+    fn key_scancode_pressed(&mut self, actx: &mut AppContext, scancode: u32) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called on every physical key release. See `key_scancode_pressed`.
+    fn key_scancode_released(&mut self, actx: &mut AppContext, scancode: u32) -> Result<()> {
         Ok(())
     }
 
@@ -143,6 +159,40 @@ where
     ) -> Result<()> {
         Ok(())
     }
+
+    /// Called whenever any other `Game` method returns `Err`, naming
+    /// which method (`origin`) produced it.
+    ///
+    /// Returning `true` means the error was handled and the game
+    /// should keep running; returning `false` (the default) makes the
+    /// event loop exit. The error itself is not returned from `run`.
+    fn on_error(&mut self, actx: &mut AppContext, origin: Origin, err: crate::anyhow::Error) -> bool {
+        false
+    }
+}
+
+/// Identifies which `Game` method produced an error passed to
+/// `Game::on_error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Update,
+    FixedUpdate,
+    Render,
+    Resize,
+    Char,
+    KeyPressed,
+    KeyReleased,
+    KeyScancodePressed,
+    KeyScancodeReleased,
+    MouseMoved,
+    MouseButtonPressed,
+    MouseButtonReleased,
+    Scroll,
+    GamepadConnected,
+    GamepadDisconnected,
+    GamepadButtonPressed,
+    GamepadButtonReleased,
+    GamepadAxisChanged,
 }
 
 #[derive(Debug, Clone)]
@@ -169,6 +219,17 @@ pub struct Options {
     ///
     /// By default, set to 1.0
     pub scroll_pixel_factor: f32,
+
+    /// When set to a positive rate, enables `Game::fixed_update`: the
+    /// window loop accumulates elapsed wall-clock time and calls
+    /// `fixed_update` zero or more times per frame at this rate
+    /// (clamped to a maximum number of steps per frame to avoid a
+    /// spiral of death), while `update`/`render` continue to run once
+    /// per frame as usual. A zero or negative value is treated the same
+    /// as `None`.
+    ///
+    /// By default, set to `None` (fixed_update is never called)
+    pub fixed_update_hz: Option<f32>,
 }
 
 impl Default for Options {
@@ -176,6 +237,7 @@ impl Default for Options {
         Self {
             enable_gamepad: true,
             scroll_pixel_factor: 1.0,
+            fixed_update_hz: None,
         }
     }
 }
@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::fnv::FnvHashSet;
+
+/// A symbolic key on the keyboard.
+///
+/// This is a thin wrapper around winit's virtual key codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Key0,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Escape,
+    Space,
+    Return,
+    Tab,
+    Backspace,
+    Up,
+    Down,
+    Left,
+    Right,
+    LShift,
+    RShift,
+    LControl,
+    RControl,
+    LAlt,
+    RAlt,
+    LWin,
+    RWin,
+    Comma,
+    Period,
+    Slash,
+    Semicolon,
+    Apostrophe,
+    Minus,
+    Equals,
+    LBracket,
+    RBracket,
+    Backslash,
+    Grave,
+}
+
+/// Which modifier keys were held down at the time of an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyMods {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub logo: bool,
+}
+
+/// A button on a mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u16),
+}
+
+/// A button on a gamepad.
+///
+/// Mirrors the buttons gilrs recognizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// An analog axis on a gamepad.
+///
+/// Mirrors the axes gilrs recognizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftZ,
+    RightZ,
+    DPadX,
+    DPadY,
+}
+
+/// Identifies a connected gamepad.
+///
+/// Wraps gilrs' own id type so that callers don't need to depend on
+/// gilrs directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeviceId(pub(crate) gilrs::GamepadId);
+
+/// A physical source that can be bound to a logical action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UserInput {
+    Key(Key),
+    MouseButton(MouseButton),
+    GamepadButton(GamepadButton),
+}
+
+/// How a logical action is bound to physical inputs.
+#[derive(Debug, Clone)]
+pub enum Binding {
+    /// Fires while the single input is held.
+    Single(UserInput),
+    /// Fires only while every input in the chord is held at once.
+    Chord(Vec<UserInput>),
+    /// An analog value taken directly from a gamepad axis.
+    GamepadAxis(Axis),
+    /// A virtual 2d axis built by summing +1/-1 contributions from four
+    /// keys (e.g. WASD or the arrow keys), clamped to a unit vector.
+    KeyAxisPair {
+        up: Key,
+        down: Key,
+        left: Key,
+        right: Key,
+    },
+}
+
+/// Marker trait for user-defined logical action enums.
+///
+/// Blanket-implemented for any `Copy` type that can be used as a
+/// `HashSet`/`HashMap` key and stored for the life of the program.
+pub trait Action: Copy + Eq + Hash + 'static {}
+
+impl<T: Copy + Eq + Hash + 'static> Action for T {}
+
+/// Maps physical inputs to a user-defined set of logical actions.
+///
+/// `ActionMap` is driven automatically by yagl's event dispatch once
+/// installed on the `AppContext` via `AppContext::set_action_map`; games
+/// then query it with `actx.actions::<A>()`.
+pub struct ActionMap<A: Action> {
+    bindings: HashMap<A, Binding>,
+    current: HashSet<A>,
+    previous: HashSet<A>,
+    values: HashMap<A, f32>,
+    axis_pairs: HashMap<A, [f32; 2]>,
+}
+
+impl<A: Action> ActionMap<A> {
+    pub fn new() -> Self {
+        Self {
+            bindings: HashMap::new(),
+            current: HashSet::new(),
+            previous: HashSet::new(),
+            values: HashMap::new(),
+            axis_pairs: HashMap::new(),
+        }
+    }
+
+    /// Associates a logical action with a binding, replacing any
+    /// existing binding for that action.
+    pub fn bind(&mut self, action: A, binding: Binding) {
+        self.bindings.insert(action, binding);
+    }
+
+    /// Whether `action` is currently held down.
+    pub fn pressed(&self, action: A) -> bool {
+        self.current.contains(&action)
+    }
+
+    /// Whether `action` transitioned from released to pressed this tick.
+    pub fn just_pressed(&self, action: A) -> bool {
+        self.current.contains(&action) && !self.previous.contains(&action)
+    }
+
+    /// Whether `action` transitioned from pressed to released this tick.
+    pub fn just_released(&self, action: A) -> bool {
+        !self.current.contains(&action) && self.previous.contains(&action)
+    }
+
+    /// The analog value of `action`, in `[0.0, 1.0]` for a digital
+    /// binding, or the raw axis value for a `GamepadAxis` binding.
+    /// Always `0.0` for a `KeyAxisPair` binding; use `axis_pair` for
+    /// those instead.
+    pub fn value(&self, action: A) -> f32 {
+        self.values.get(&action).copied().unwrap_or(0.0)
+    }
+
+    /// The 2d analog value of `action` for a `KeyAxisPair` binding,
+    /// clamped to a unit vector.
+    pub fn axis_pair(&self, action: A) -> [f32; 2] {
+        self.axis_pairs.get(&action).copied().unwrap_or([0.0, 0.0])
+    }
+
+    fn is_input_down(
+        &self,
+        input: &UserInput,
+        keys: &FnvHashSet<Key>,
+        mouse_buttons: &FnvHashSet<MouseButton>,
+        gamepad_buttons: &FnvHashSet<GamepadButton>,
+    ) -> bool {
+        match input {
+            UserInput::Key(key) => keys.contains(key),
+            UserInput::MouseButton(button) => mouse_buttons.contains(button),
+            UserInput::GamepadButton(button) => gamepad_buttons.contains(button),
+        }
+    }
+
+    /// Recomputes `current`/`values`/`axis_pairs` from the live set of
+    /// held keys, mouse buttons, and gamepad buttons (the latter two
+    /// device-agnostic: a binding fires if any connected device reports
+    /// the button down). Chords only fire when every member is held;
+    /// when two bindings overlap and are both satisfied, the one with
+    /// more inputs wins.
+    fn recompute(
+        &mut self,
+        keys: &FnvHashSet<Key>,
+        mouse_buttons: &FnvHashSet<MouseButton>,
+        gamepad_buttons: &FnvHashSet<GamepadButton>,
+        gamepad_axes: &HashMap<Axis, f32>,
+    ) {
+        self.current.clear();
+        self.values.clear();
+        self.axis_pairs.clear();
+        for (action, binding) in &self.bindings {
+            match binding {
+                Binding::Single(input) => {
+                    if self.is_input_down(input, keys, mouse_buttons, gamepad_buttons) {
+                        self.current.insert(*action);
+                        self.values.insert(*action, 1.0);
+                    }
+                }
+                Binding::Chord(inputs) => {
+                    if !inputs.is_empty()
+                        && inputs
+                            .iter()
+                            .all(|i| self.is_input_down(i, keys, mouse_buttons, gamepad_buttons))
+                    {
+                        self.current.insert(*action);
+                        self.values.insert(*action, 1.0);
+                    }
+                }
+                Binding::GamepadAxis(axis) => {
+                    let value = gamepad_axes.get(axis).copied().unwrap_or(0.0);
+                    self.values.insert(*action, value);
+                    if value != 0.0 {
+                        self.current.insert(*action);
+                    }
+                }
+                Binding::KeyAxisPair {
+                    up,
+                    down,
+                    left,
+                    right,
+                } => {
+                    let x = (keys.contains(right) as i32 - keys.contains(left) as i32) as f32;
+                    let y = (keys.contains(up) as i32 - keys.contains(down) as i32) as f32;
+                    let mut v = [x, y];
+                    let len = (v[0] * v[0] + v[1] * v[1]).sqrt();
+                    if len > 1.0 {
+                        v[0] /= len;
+                        v[1] /= len;
+                    }
+                    if v != [0.0, 0.0] {
+                        self.current.insert(*action);
+                    }
+                    self.axis_pairs.insert(*action, v);
+                }
+            }
+        }
+        // Resolve clashes where one satisfied binding's inputs are a
+        // subset of another satisfied binding's inputs: keep only the
+        // action with the larger binding.
+        let satisfied: Vec<A> = self.current.iter().copied().collect();
+        for a in &satisfied {
+            for b in &satisfied {
+                if a == b {
+                    continue;
+                }
+                if let (Some(ba), Some(bb)) = (self.bindings.get(a), self.bindings.get(b)) {
+                    if binding_len(ba) < binding_len(bb) && is_subset(ba, bb) {
+                        self.current.remove(a);
+                        self.values.remove(a);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Snapshots `current` into `previous`. Called once per `update`
+    /// tick by the window event loop.
+    pub(crate) fn tick(
+        &mut self,
+        keys: &FnvHashSet<Key>,
+        mouse_buttons: &FnvHashSet<MouseButton>,
+        gamepad_buttons: &FnvHashSet<GamepadButton>,
+        gamepad_axes: &HashMap<Axis, f32>,
+    ) {
+        self.previous = std::mem::take(&mut self.current);
+        self.recompute(keys, mouse_buttons, gamepad_buttons, gamepad_axes);
+    }
+}
+
+impl<A: Action> Default for ActionMap<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn binding_len(binding: &Binding) -> usize {
+    match binding {
+        Binding::Single(_) => 1,
+        Binding::Chord(inputs) => inputs.len(),
+        Binding::GamepadAxis(_) => 1,
+        Binding::KeyAxisPair { .. } => 4,
+    }
+}
+
+fn binding_inputs(binding: &Binding) -> Vec<UserInput> {
+    match binding {
+        Binding::Single(input) => vec![*input],
+        Binding::Chord(inputs) => inputs.clone(),
+        Binding::GamepadAxis(_) | Binding::KeyAxisPair { .. } => Vec::new(),
+    }
+}
+
+fn is_subset(smaller: &Binding, larger: &Binding) -> bool {
+    let smaller = binding_inputs(smaller);
+    if smaller.is_empty() {
+        return false;
+    }
+    let larger = binding_inputs(larger);
+    smaller.iter().all(|i| larger.contains(i))
+}
+
+/// Type-erased handle to an `ActionMap<A>` so `AppContext` can hold one
+/// without becoming generic itself.
+pub(crate) trait ActionMapHandle: std::any::Any {
+    fn tick(
+        &mut self,
+        keys: &FnvHashSet<Key>,
+        mouse_buttons: &FnvHashSet<MouseButton>,
+        gamepad_buttons: &FnvHashSet<GamepadButton>,
+        gamepad_axes: &HashMap<Axis, f32>,
+    );
+    fn as_any(&self) -> &dyn std::any::Any;
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+}
+
+impl<A: Action> ActionMapHandle for ActionMap<A> {
+    fn tick(
+        &mut self,
+        keys: &FnvHashSet<Key>,
+        mouse_buttons: &FnvHashSet<MouseButton>,
+        gamepad_buttons: &FnvHashSet<GamepadButton>,
+        gamepad_axes: &HashMap<Axis, f32>,
+    ) {
+        ActionMap::tick(self, keys, mouse_buttons, gamepad_buttons, gamepad_axes)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
@@ -1,24 +1,39 @@
 extern crate a2d;
 extern crate anyhow;
+extern crate fnv;
 extern crate futures;
 extern crate gilrs;
+extern crate rodio;
 
 use a2d::winit;
 
+mod audio;
 mod context;
 mod game;
 mod input;
+mod time;
 mod window;
 
+pub use audio::AudioContext;
+pub use audio::PlayOptions;
+pub use audio::Sound;
+pub use audio::Source;
 pub use context::AppContext;
 pub use context::RenderContext;
 pub use game::Game;
 pub use game::Options;
+pub use game::Origin;
+pub use input::Action;
+pub use input::ActionMap;
 pub use input::Axis;
+pub use input::Binding;
 pub use input::DeviceId;
 pub use input::GamepadButton;
 pub use input::Key;
+pub use input::KeyMods;
 pub use input::MouseButton;
+pub use input::UserInput;
+pub use time::TimeContext;
 pub use window::run;
 pub use window::Window;
 
@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use std::time::Instant;
+
+// Window size for the fps moving average.
+const FPS_WINDOW: usize = 30;
+
+/// Frame timing: delta time, a smoothed fps estimate, tick count, and
+/// total elapsed time, exposed to games as `actx.time()`.
+pub struct TimeContext {
+    start: Instant,
+    last_tick: Instant,
+    delta: Duration,
+    ticks: u64,
+    recent_frame_times: VecDeque<Duration>,
+}
+
+impl TimeContext {
+    pub(crate) fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            start: now,
+            last_tick: now,
+            delta: Duration::from_secs(0),
+            ticks: 0,
+            recent_frame_times: VecDeque::with_capacity(FPS_WINDOW),
+        }
+    }
+
+    /// Advances the clock by one `update` tick. Called once per frame
+    /// by the window event loop, before `Game::update` runs.
+    pub(crate) fn tick(&mut self) {
+        let now = Instant::now();
+        self.delta = now - self.last_tick;
+        self.last_tick = now;
+        self.ticks += 1;
+        if self.recent_frame_times.len() == FPS_WINDOW {
+            self.recent_frame_times.pop_front();
+        }
+        self.recent_frame_times.push_back(self.delta);
+    }
+
+    /// Time elapsed since the previous `update` tick.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Frames per second, averaged over a sliding window of recent
+    /// frame times.
+    pub fn fps(&self) -> f64 {
+        let total: Duration = self.recent_frame_times.iter().sum();
+        if total.is_zero() {
+            return 0.0;
+        }
+        self.recent_frame_times.len() as f64 / total.as_secs_f64()
+    }
+
+    /// The number of `update` ticks since the program started.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Total time elapsed since the program started.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
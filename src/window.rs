@@ -0,0 +1,517 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::anyhow::Result;
+use crate::context::RenderContext;
+use crate::input::Key;
+use crate::winit::event::ElementState;
+use crate::winit::event::Event;
+use crate::winit::event::ModifiersState;
+use crate::winit::event::MouseButton as WinitMouseButton;
+use crate::winit::event::VirtualKeyCode;
+use crate::winit::event::WindowEvent;
+use crate::winit::event_loop::ControlFlow;
+use crate::winit::event_loop::EventLoop;
+use crate::AppContext;
+use crate::Axis;
+use crate::DeviceId;
+use crate::Game;
+use crate::GamepadButton;
+use crate::KeyMods;
+use crate::MouseButton;
+use crate::Origin;
+
+/// Owns the OS window. No a2d rendering surface is created or held
+/// here.
+pub struct Window {
+    window: a2d::winit::window::Window,
+}
+
+impl Window {
+    fn new(window: a2d::winit::window::Window) -> Self {
+        Self { window }
+    }
+
+    /// The raw winit window, for anything not otherwise exposed.
+    pub fn raw(&self) -> &a2d::winit::window::Window {
+        &self.window
+    }
+}
+
+/// Events produced by the background gamepad-polling thread.
+pub(crate) enum GamepadEvent {
+    Connected(DeviceId, bool),
+    Disconnected(DeviceId),
+    ButtonPressed(DeviceId, GamepadButton),
+    ButtonReleased(DeviceId, GamepadButton),
+    AxisChanged(DeviceId, Axis, f32),
+}
+
+/// Commands sent from `AppContext` to the background gamepad thread.
+pub(crate) enum GamepadCommand {
+    SetRumble {
+        dev: DeviceId,
+        strong: f32,
+        weak: f32,
+        duration: Duration,
+    },
+    StopRumble {
+        dev: DeviceId,
+    },
+}
+
+/// Spawns the background thread that owns the `gilrs::Gilrs` instance,
+/// forwards translated events back to the window loop, and plays
+/// force-feedback effects on request.
+///
+/// This is its own thread because winit does not integrate gamepad
+/// polling into its event loop, so gilrs must be driven independently
+/// without blocking frame delivery.
+fn spawn_gamepad_thread() -> Result<(mpsc::Receiver<GamepadEvent>, mpsc::Sender<GamepadCommand>)> {
+    let (event_tx, event_rx) = mpsc::channel();
+    let (cmd_tx, cmd_rx) = mpsc::channel::<GamepadCommand>();
+    let mut gilrs = gilrs::Gilrs::new().map_err(|err| crate::anyhow::anyhow!("{}", err))?;
+    thread::spawn(move || {
+        let mut rumble_effects = std::collections::HashMap::new();
+        // gilrs only emits `Connected` via `next_event()` for hotplug;
+        // pads already plugged in at launch need to be seeded here so
+        // their rumble support is known from the start.
+        for (id, pad) in gilrs.gamepads() {
+            let translated = GamepadEvent::Connected(DeviceId(id), pad.is_ff_supported());
+            if event_tx.send(translated).is_err() {
+                return;
+            }
+        }
+        loop {
+            while let Some(event) = gilrs.next_event() {
+                let dev = DeviceId(event.id);
+                let translated = match event.event {
+                    gilrs::EventType::Connected => {
+                        let supports_rumble = gilrs.gamepad(event.id).is_ff_supported();
+                        Some(GamepadEvent::Connected(dev, supports_rumble))
+                    }
+                    gilrs::EventType::Disconnected => {
+                        rumble_effects.remove(&event.id);
+                        Some(GamepadEvent::Disconnected(dev))
+                    }
+                    gilrs::EventType::ButtonPressed(button, _) => translate_gamepad_button(button)
+                        .map(|b| GamepadEvent::ButtonPressed(dev, b)),
+                    gilrs::EventType::ButtonReleased(button, _) => translate_gamepad_button(button)
+                        .map(|b| GamepadEvent::ButtonReleased(dev, b)),
+                    gilrs::EventType::AxisChanged(axis, value, _) => {
+                        translate_axis(axis).map(|a| GamepadEvent::AxisChanged(dev, a, value))
+                    }
+                    _ => None,
+                };
+                if let Some(translated) = translated {
+                    if event_tx.send(translated).is_err() {
+                        return;
+                    }
+                }
+            }
+
+            while let Ok(cmd) = cmd_rx.try_recv() {
+                match cmd {
+                    GamepadCommand::SetRumble {
+                        dev,
+                        strong,
+                        weak,
+                        duration,
+                    } => {
+                        if let Ok(effect) = gilrs::ff::EffectBuilder::new()
+                            .add_effect(gilrs::ff::BaseEffect {
+                                kind: gilrs::ff::BaseEffectType::Strong { magnitude: to_u16(strong) },
+                                scheduling: gilrs::ff::Replay {
+                                    play_for: gilrs::ff::Ticks::from_ms(duration.as_millis() as u32),
+                                    ..Default::default()
+                                },
+                                envelope: Default::default(),
+                            })
+                            .add_effect(gilrs::ff::BaseEffect {
+                                kind: gilrs::ff::BaseEffectType::Weak { magnitude: to_u16(weak) },
+                                scheduling: gilrs::ff::Replay {
+                                    play_for: gilrs::ff::Ticks::from_ms(duration.as_millis() as u32),
+                                    ..Default::default()
+                                },
+                                envelope: Default::default(),
+                            })
+                            .add_gamepad(&gilrs, dev.0)
+                            .finish(&mut gilrs)
+                        {
+                            let _ = effect.play();
+                            rumble_effects.insert(dev.0, effect);
+                        }
+                    }
+                    GamepadCommand::StopRumble { dev } => {
+                        if let Some(effect) = rumble_effects.remove(&dev.0) {
+                            let _ = effect.stop();
+                        }
+                    }
+                }
+            }
+
+            thread::sleep(Duration::from_millis(4));
+        }
+    });
+    Ok((event_rx, cmd_tx))
+}
+
+fn to_u16(magnitude: f32) -> u16 {
+    (magnitude.clamp(0.0, 1.0) * u16::MAX as f32) as u16
+}
+
+// Caps the number of `fixed_update` calls per frame so a long stall
+// (e.g. a breakpoint or a slow resize) can't spiral into an
+// ever-growing backlog of catch-up steps.
+const MAX_FIXED_STEPS_PER_FRAME: u32 = 8;
+
+fn translate_key(key: VirtualKeyCode) -> Option<Key> {
+    use VirtualKeyCode::*;
+    Some(match key {
+        A => Key::A,
+        B => Key::B,
+        C => Key::C,
+        D => Key::D,
+        E => Key::E,
+        F => Key::F,
+        G => Key::G,
+        H => Key::H,
+        I => Key::I,
+        J => Key::J,
+        K => Key::K,
+        L => Key::L,
+        M => Key::M,
+        N => Key::N,
+        O => Key::O,
+        P => Key::P,
+        Q => Key::Q,
+        R => Key::R,
+        S => Key::S,
+        T => Key::T,
+        U => Key::U,
+        V => Key::V,
+        W => Key::W,
+        X => Key::X,
+        Y => Key::Y,
+        Z => Key::Z,
+        Key0 => Key::Key0,
+        Key1 => Key::Key1,
+        Key2 => Key::Key2,
+        Key3 => Key::Key3,
+        Key4 => Key::Key4,
+        Key5 => Key::Key5,
+        Key6 => Key::Key6,
+        Key7 => Key::Key7,
+        Key8 => Key::Key8,
+        Key9 => Key::Key9,
+        F1 => Key::F1,
+        F2 => Key::F2,
+        F3 => Key::F3,
+        F4 => Key::F4,
+        F5 => Key::F5,
+        F6 => Key::F6,
+        F7 => Key::F7,
+        F8 => Key::F8,
+        F9 => Key::F9,
+        F10 => Key::F10,
+        F11 => Key::F11,
+        F12 => Key::F12,
+        Escape => Key::Escape,
+        Space => Key::Space,
+        Return => Key::Return,
+        Tab => Key::Tab,
+        Back => Key::Backspace,
+        Up => Key::Up,
+        Down => Key::Down,
+        Left => Key::Left,
+        Right => Key::Right,
+        LShift => Key::LShift,
+        RShift => Key::RShift,
+        LControl => Key::LControl,
+        RControl => Key::RControl,
+        LAlt => Key::LAlt,
+        RAlt => Key::RAlt,
+        LWin => Key::LWin,
+        RWin => Key::RWin,
+        Comma => Key::Comma,
+        Period => Key::Period,
+        Slash => Key::Slash,
+        Semicolon => Key::Semicolon,
+        Apostrophe => Key::Apostrophe,
+        Minus => Key::Minus,
+        Equals => Key::Equals,
+        LBracket => Key::LBracket,
+        RBracket => Key::RBracket,
+        Backslash => Key::Backslash,
+        Grave => Key::Grave,
+        _ => return None,
+    })
+}
+
+fn translate_mods(mods: ModifiersState) -> KeyMods {
+    KeyMods {
+        shift: mods.shift(),
+        ctrl: mods.ctrl(),
+        alt: mods.alt(),
+        logo: mods.logo(),
+    }
+}
+
+fn translate_mouse_button(button: WinitMouseButton) -> MouseButton {
+    match button {
+        WinitMouseButton::Left => MouseButton::Left,
+        WinitMouseButton::Right => MouseButton::Right,
+        WinitMouseButton::Middle => MouseButton::Middle,
+        WinitMouseButton::Other(id) => MouseButton::Other(id),
+    }
+}
+
+fn translate_gamepad_button(button: gilrs::Button) -> Option<GamepadButton> {
+    use gilrs::Button::*;
+    Some(match button {
+        South => GamepadButton::South,
+        East => GamepadButton::East,
+        North => GamepadButton::North,
+        West => GamepadButton::West,
+        LeftTrigger => GamepadButton::LeftTrigger,
+        LeftTrigger2 => GamepadButton::LeftTrigger2,
+        RightTrigger => GamepadButton::RightTrigger,
+        RightTrigger2 => GamepadButton::RightTrigger2,
+        Select => GamepadButton::Select,
+        Start => GamepadButton::Start,
+        Mode => GamepadButton::Mode,
+        LeftThumb => GamepadButton::LeftThumb,
+        RightThumb => GamepadButton::RightThumb,
+        DPadUp => GamepadButton::DPadUp,
+        DPadDown => GamepadButton::DPadDown,
+        DPadLeft => GamepadButton::DPadLeft,
+        DPadRight => GamepadButton::DPadRight,
+        _ => return None,
+    })
+}
+
+fn translate_axis(axis: gilrs::Axis) -> Option<Axis> {
+    use gilrs::Axis::*;
+    Some(match axis {
+        LeftStickX => Axis::LeftStickX,
+        LeftStickY => Axis::LeftStickY,
+        RightStickX => Axis::RightStickX,
+        RightStickY => Axis::RightStickY,
+        LeftZ => Axis::LeftZ,
+        RightZ => Axis::RightZ,
+        DPadX => Axis::DPadX,
+        DPadY => Axis::DPadY,
+        _ => return None,
+    })
+}
+
+/// Routes a callback's `Err` through `Game::on_error`, exiting the
+/// event loop unless the game reports it handled the error.
+fn handle_result<G: Game>(
+    result: Result<()>,
+    origin: Origin,
+    actx: &mut AppContext,
+    game: &mut G,
+    control_flow: &mut ControlFlow,
+) {
+    if let Err(err) = result {
+        if !game.on_error(actx, origin, err) {
+            *control_flow = ControlFlow::Exit;
+        }
+    }
+}
+
+fn dispatch_gamepad_event<G: Game>(
+    event: GamepadEvent,
+    actx: &mut AppContext,
+    game: &mut G,
+    control_flow: &mut ControlFlow,
+) {
+    match event {
+        GamepadEvent::Connected(dev, supports_rumble) => {
+            if supports_rumble {
+                actx.gamepad_rumble_supported.insert(dev);
+            }
+            let result = game.gamepad_connected(actx, dev);
+            handle_result(result, Origin::GamepadConnected, actx, game, control_flow);
+        }
+        GamepadEvent::Disconnected(dev) => {
+            actx.gamepad_rumble_supported.remove(&dev);
+            let result = game.gamepad_disconnected(actx, dev);
+            handle_result(result, Origin::GamepadDisconnected, actx, game, control_flow);
+        }
+        GamepadEvent::ButtonPressed(dev, button) => {
+            actx.gamepad_buttons_down.insert((dev, button));
+            actx.gamepad_buttons_down_any_device.insert(button);
+            let result = game.gamepad_button_pressed(actx, dev, button);
+            handle_result(result, Origin::GamepadButtonPressed, actx, game, control_flow);
+        }
+        GamepadEvent::ButtonReleased(dev, button) => {
+            actx.gamepad_buttons_down.remove(&(dev, button));
+            if !actx.gamepad_buttons_down.iter().any(|(_, b)| *b == button) {
+                actx.gamepad_buttons_down_any_device.remove(&button);
+            }
+            let result = game.gamepad_button_released(actx, dev, button);
+            handle_result(result, Origin::GamepadButtonReleased, actx, game, control_flow);
+        }
+        GamepadEvent::AxisChanged(dev, axis, value) => {
+            actx.gamepad_axes.insert((dev, axis), value);
+            actx.gamepad_axes_any_device.insert(axis, value);
+            let result = game.gamepad_axis_changed(actx, dev, axis, value);
+            handle_result(result, Origin::GamepadAxisChanged, actx, game, control_flow);
+        }
+    }
+}
+
+/// Runs the application, taking over the calling thread until the game
+/// exits.
+///
+/// `make_game` is called exactly once, after the window is created, to
+/// produce the `Game` instance that will be run. No rendering surface
+/// is set up.
+pub fn run<G: Game, F: FnOnce(&mut AppContext) -> Result<G> + 'static>(make_game: F) -> Result<()> {
+    let event_loop = EventLoop::new();
+    let winit_window = a2d::winit::window::WindowBuilder::new().build(&event_loop)?;
+    let _window = Window::new(winit_window);
+
+    let mut actx = AppContext::new()?;
+    let mut game = make_game(&mut actx)?;
+    let options = game.options();
+
+    let gamepad_events = if options.enable_gamepad {
+        let (event_rx, cmd_tx) = spawn_gamepad_thread()?;
+        actx.gamepad_commands = Some(cmd_tx);
+        Some(event_rx)
+    } else {
+        None
+    };
+    let mut mouse_pos = [0.0f32, 0.0];
+    let mut fixed_accumulator = Duration::from_secs(0);
+    let mut current_mods = KeyMods::default();
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Poll;
+
+        if let Some(rx) = &gamepad_events {
+            while let Ok(event) = rx.try_recv() {
+                dispatch_gamepad_event(event, &mut actx, &mut game, control_flow);
+            }
+        }
+
+        match event {
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::CloseRequested => {
+                    *control_flow = ControlFlow::Exit;
+                }
+                WindowEvent::Resized(size) => {
+                    let result = game.resize(&mut actx, size.width, size.height);
+                    handle_result(result, Origin::Resize, &mut actx, &mut game, control_flow);
+                }
+                WindowEvent::ReceivedCharacter(ch) => {
+                    let result = game.char(&mut actx, ch);
+                    handle_result(result, Origin::Char, &mut actx, &mut game, control_flow);
+                }
+                WindowEvent::ModifiersChanged(mods) => {
+                    current_mods = translate_mods(mods);
+                }
+                WindowEvent::KeyboardInput { input, .. } => {
+                    let key = input.virtual_keycode.and_then(translate_key);
+                    match input.state {
+                        ElementState::Pressed => {
+                            if let Some(key) = key {
+                                actx.keys_down.insert(key);
+                                let result = game.key_pressed(&mut actx, key, current_mods);
+                                handle_result(result, Origin::KeyPressed, &mut actx, &mut game, control_flow);
+                            }
+                            let result = game.key_scancode_pressed(&mut actx, input.scancode);
+                            handle_result(result, Origin::KeyScancodePressed, &mut actx, &mut game, control_flow);
+                        }
+                        ElementState::Released => {
+                            if let Some(key) = key {
+                                actx.keys_down.remove(&key);
+                                let result = game.key_released(&mut actx, key, current_mods);
+                                handle_result(result, Origin::KeyReleased, &mut actx, &mut game, control_flow);
+                            }
+                            let result = game.key_scancode_released(&mut actx, input.scancode);
+                            handle_result(result, Origin::KeyScancodeReleased, &mut actx, &mut game, control_flow);
+                        }
+                    }
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    mouse_pos = [position.x as f32, position.y as f32];
+                    actx.mouse_position = mouse_pos;
+                    let result = game.mouse_moved(&mut actx, mouse_pos);
+                    handle_result(result, Origin::MouseMoved, &mut actx, &mut game, control_flow);
+                }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    let button = translate_mouse_button(button);
+                    match state {
+                        ElementState::Pressed => {
+                            actx.mouse_buttons_down.insert(button);
+                            let result = game.mouse_button_pressed(&mut actx, mouse_pos, button);
+                            handle_result(result, Origin::MouseButtonPressed, &mut actx, &mut game, control_flow);
+                        }
+                        ElementState::Released => {
+                            actx.mouse_buttons_down.remove(&button);
+                            let result = game.mouse_button_released(&mut actx, mouse_pos, button);
+                            handle_result(result, Origin::MouseButtonReleased, &mut actx, &mut game, control_flow);
+                        }
+                    }
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let factor = options.scroll_pixel_factor;
+                    let delta = match delta {
+                        a2d::winit::event::MouseScrollDelta::LineDelta(x, y) => {
+                            [x * factor, y * factor]
+                        }
+                        a2d::winit::event::MouseScrollDelta::PixelDelta(pos) => {
+                            [pos.x as f32, pos.y as f32]
+                        }
+                    };
+                    let result = game.scroll(&mut actx, mouse_pos, delta);
+                    handle_result(result, Origin::Scroll, &mut actx, &mut game, control_flow);
+                }
+                _ => {}
+            },
+            Event::MainEventsCleared => {
+                actx.tick_time();
+                if let Some(hz) = options.fixed_update_hz.filter(|hz| *hz > 0.0) {
+                    fixed_accumulator += actx.time().delta();
+                    let fixed_dt = Duration::from_secs_f32(1.0 / hz);
+                    let mut steps = 0;
+                    while fixed_accumulator >= fixed_dt && steps < MAX_FIXED_STEPS_PER_FRAME {
+                        let result = game.fixed_update(&mut actx);
+                        handle_result(result, Origin::FixedUpdate, &mut actx, &mut game, control_flow);
+                        if *control_flow == ControlFlow::Exit {
+                            return;
+                        }
+                        fixed_accumulator -= fixed_dt;
+                        steps += 1;
+                    }
+                    if fixed_accumulator >= fixed_dt {
+                        // Drop whatever the cap left over instead of
+                        // carrying it into later frames.
+                        fixed_accumulator = Duration::from_secs(0);
+                    }
+                }
+
+                actx.tick_action_map();
+                let update_result = game.update(&mut actx);
+                actx.rotate_input_snapshot();
+                handle_result(update_result, Origin::Update, &mut actx, &mut game, control_flow);
+                if *control_flow == ControlFlow::Exit {
+                    return;
+                }
+                let mut rctx = RenderContext::new(&mut actx);
+                let render_result = game.render(&mut rctx);
+                handle_result(render_result, Origin::Render, &mut actx, &mut game, control_flow);
+            }
+            _ => {}
+        }
+
+        if actx.should_exit {
+            *control_flow = ControlFlow::Exit;
+        }
+    });
+}